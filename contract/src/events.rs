@@ -0,0 +1,44 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::traits::{ProposalId, ProposalKind};
+
+const STANDARD_NAME: &str = "votron-governance";
+const STANDARD_VERSION: &str = "1.0.0";
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum GovernanceEvent {
+    AgentRegistered {
+        account_id: AccountId,
+        codehash: String,
+    },
+    ProposalApproved {
+        proposal_id: ProposalId,
+        kind: ProposalKind,
+    },
+    ProposalFailed {
+        proposal_id: ProposalId,
+        reason: String,
+    },
+    CodehashApproved {
+        codehash: String,
+    },
+    CodehashRevoked {
+        codehash: String,
+    },
+    Paused {
+        paused: bool,
+    },
+}
+
+// NEP-297 standard event log: https://github.com/near/NEPs/blob/master/neps/nep-0297.md
+pub fn emit(event: &GovernanceEvent) {
+    let mut payload = serde_json::to_value(event).expect("failed to serialize event");
+    let fields = payload.as_object_mut().expect("event must serialize to an object");
+    fields.insert("standard".to_string(), STANDARD_NAME.into());
+    fields.insert("version".to_string(), STANDARD_VERSION.into());
+
+    env::log_str(&format!("EVENT_JSON:{}", payload));
+}
@@ -0,0 +1,31 @@
+use hex::encode;
+use near_sdk::{env, near, AccountId};
+
+use crate::traits::{ProposalId, ProposalKind, Vote};
+
+pub const ZERO_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub enum HashchainAction {
+    ApproveProposal,
+    Vote(Vote),
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct ActionRecord {
+    pub seq: u64,
+    pub agent: AccountId,
+    pub proposal_id: ProposalId,
+    pub kind: ProposalKind,
+    pub action: HashchainAction,
+    pub block_timestamp: u64,
+}
+
+// head = sha256(prev_head || borsh(record))
+pub fn next_head(prev_head: &str, record: &ActionRecord) -> String {
+    let mut preimage = hex::decode(prev_head).expect("invalid hashchain head");
+    preimage.extend(borsh::to_vec(record).expect("failed to serialize action record"));
+    encode(env::sha256(&preimage))
+}
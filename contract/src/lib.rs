@@ -3,26 +3,33 @@ use near_sdk::{
     env::{self, block_timestamp},
     near, require,
     store::{IterableMap, IterableSet},
-    AccountId, Gas, NearToken, PanicOnDefault, Promise, PromiseError,
+    AccountId, Gas, NearToken, PanicOnDefault, Promise, PromiseError, PromiseOrValue,
 };
 
 use dcap_qvl::{verify, QuoteCollateralV3};
 
 mod collateral;
+mod events;
+mod hashchain;
 mod traits;
-use traits::{ext_self, ext_voting, ProposalId, SelfCallbacks};
+use events::GovernanceEvent;
+use hashchain::{ActionRecord, HashchainAction, ZERO_HASH};
+use traits::{ext_self, ext_voting, ProposalId, ProposalKind, SelfCallbacks, Vote};
 
 // Governance constants
 const GAS_FOR_GOVERNANCE: Gas = Gas::from_tgas(50);
 const GAS_FOR_CALLBACK: Gas = Gas::from_tgas(30);
 const YOCTO_DEPOSIT: NearToken = NearToken::from_yoctonear(1);
 const VOTING_CONTRACT: &str = "shade.ballotbox.testnet";
+const DEFAULT_ATTESTATION_TTL_SEC: u64 = 60 * 60 * 24; // 24 hours
+const DEFAULT_EVENTS_CAPACITY: u64 = 1000;
 
 #[near(serializers = [json, borsh])]
 #[derive(Clone)]
 pub struct Worker {
     checksum: String,
     codehash: String,
+    registered_at: u64,
 }
 
 #[near(contract_state)]
@@ -31,6 +38,17 @@ pub struct Contract {
     pub owner_id: AccountId,
     pub approved_codehashes: IterableSet<String>,
     pub worker_by_account_id: IterableMap<AccountId, Worker>,
+    pub threshold: u32,
+    pub endorsements_by_proposal_id: IterableMap<(ProposalId, ProposalKind), IterableSet<AccountId>>,
+    pub submitted_proposals: IterableSet<(ProposalId, ProposalKind)>,
+    pub head: String,
+    pub seq: u64,
+    pub attestation_ttl_sec: u64,
+    pub allowed_codehashes_by_kind: IterableMap<ProposalKind, IterableSet<String>>,
+    pub events_by_index: IterableMap<u64, GovernanceEvent>,
+    pub event_count: u64,
+    pub events_capacity: u64,
+    pub paused: bool,
 }
 
 #[near]
@@ -42,6 +60,20 @@ impl Contract {
             owner_id,
             approved_codehashes: IterableSet::new(b"a"),
             worker_by_account_id: IterableMap::new(b"b"),
+            threshold: 1,
+            endorsements_by_proposal_id: IterableMap::new(b"c"),
+            submitted_proposals: IterableSet::new(b"g"),
+            head: ZERO_HASH.to_string(),
+            seq: 0,
+            attestation_ttl_sec: DEFAULT_ATTESTATION_TTL_SEC,
+            allowed_codehashes_by_kind: IterableMap::new(b"d"),
+            // Note: "n" and "k" are reserved as leading bytes for the dynamically-built
+            // per-(proposal,kind) and per-kind nested collection prefixes below; pick a
+            // letter no nested prefix starts with to avoid storage key collisions.
+            events_by_index: IterableMap::new(b"f"),
+            event_count: 0,
+            events_capacity: DEFAULT_EVENTS_CAPACITY,
+            paused: false,
         }
     }
 
@@ -49,7 +81,61 @@ impl Contract {
 
     pub fn approve_codehash(&mut self, codehash: String) {
         self.require_owner();
-        self.approved_codehashes.insert(codehash);
+        self.approved_codehashes.insert(codehash.clone());
+        // Default-kind proposals inherit the flat approved_codehashes policy so this
+        // stays backwards compatible; Funding/ParameterChange still need an explicit
+        // allow_codehash_for_kind call to narrow who may act on them.
+        self.allow_codehash_for_kind(ProposalKind::Default, codehash.clone());
+        self.record_event(GovernanceEvent::CodehashApproved { codehash });
+    }
+
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.require_owner();
+        require!(threshold > 0, "threshold must be at least 1");
+        self.threshold = threshold;
+    }
+
+    pub fn set_attestation_ttl_sec(&mut self, attestation_ttl_sec: u64) {
+        self.require_owner();
+        self.attestation_ttl_sec = attestation_ttl_sec;
+    }
+
+    pub fn allow_codehash_for_kind(&mut self, kind: ProposalKind, codehash: String) {
+        self.require_owner();
+        require!(self.approved_codehashes.contains(&codehash));
+        if !self.allowed_codehashes_by_kind.contains_key(&kind) {
+            self.allowed_codehashes_by_kind.insert(
+                kind,
+                IterableSet::new(format!("k{}", self.allowed_codehashes_by_kind.len()).into_bytes()),
+            );
+        }
+        self.allowed_codehashes_by_kind
+            .get_mut(&kind)
+            .unwrap()
+            .insert(codehash);
+    }
+
+    pub fn set_events_capacity(&mut self, events_capacity: u64) {
+        self.require_owner();
+        require!(events_capacity > 0, "events_capacity must be at least 1");
+        self.events_capacity = events_capacity;
+    }
+
+    pub fn revoke_codehash(&mut self, codehash: String) {
+        self.require_owner();
+        self.approved_codehashes.remove(&codehash);
+        for kind in [ProposalKind::Default, ProposalKind::Funding, ProposalKind::ParameterChange] {
+            if let Some(allowed) = self.allowed_codehashes_by_kind.get_mut(&kind) {
+                allowed.remove(&codehash);
+            }
+        }
+        self.record_event(GovernanceEvent::CodehashRevoked { codehash });
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.require_owner();
+        self.paused = paused;
+        self.record_event(GovernanceEvent::Paused { paused });
     }
 
     // Agent registration with full attestation verification
@@ -62,6 +148,8 @@ impl Contract {
         checksum: String,
         tcb_info: String,
     ) -> bool {
+        require!(!self.paused, "contract is paused");
+
         let collateral = collateral::get_collateral(collateral);
         let quote = decode(quote_hex).unwrap();
         let now = block_timestamp() / 1000000000;
@@ -85,32 +173,91 @@ impl Contract {
 
         let predecessor = env::predecessor_account_id();
         self.worker_by_account_id.insert(
-            predecessor,
+            predecessor.clone(),
             Worker {
                 checksum,
-                codehash: shade_agent_app_image,
+                codehash: shade_agent_app_image.clone(),
+                registered_at: now,
             },
         );
 
+        self.record_event(GovernanceEvent::AgentRegistered {
+            account_id: predecessor,
+            codehash: shade_agent_app_image,
+        });
+
         true
     }
 
     // Governance functions
 
-    pub fn approve_proposal(&mut self, proposal_id: ProposalId, voting_start_time_sec: Option<u32>) -> Promise {
-        self.require_approved_codehash();
+    pub fn approve_proposal(&mut self, proposal_id: ProposalId, kind: ProposalKind, voting_start_time_sec: Option<u32>) -> PromiseOrValue<bool> {
+        self.require_approved_codehash_for_kind(kind);
 
-        env::log_str(&format!("🤖 PROXY: Agent approving proposal {}", proposal_id));
+        let endorser = env::predecessor_account_id();
+        let endorsement_key = (proposal_id, kind);
+        if !self.endorsements_by_proposal_id.contains_key(&endorsement_key) {
+            self.endorsements_by_proposal_id.insert(
+                endorsement_key,
+                IterableSet::new(format!("n{}-{:?}", proposal_id, kind).into_bytes()),
+            );
+        }
+        let endorsements = self.endorsements_by_proposal_id.get_mut(&endorsement_key).unwrap();
+        endorsements.insert(endorser.clone());
+        let endorsement_count = endorsements.len();
+
+        env::log_str(&format!(
+            "🤖 PROXY: Agent {} endorsed proposal {} ({}/{})",
+            endorser, proposal_id, endorsement_count, self.threshold
+        ));
+
+        if endorsement_count < self.threshold {
+            return PromiseOrValue::Value(false);
+        }
+
+        if self.submitted_proposals.contains(&endorsement_key) {
+            env::log_str(&format!(
+                "🤖 PROXY: Proposal {} already forwarded, not forwarding again",
+                proposal_id
+            ));
+            return PromiseOrValue::Value(false);
+        }
+        self.submitted_proposals.insert(endorsement_key);
+
+        env::log_str(&format!("🤖 PROXY: Quorum reached, forwarding proposal {}", proposal_id));
+
+        self.record_action(proposal_id, kind, HashchainAction::ApproveProposal);
+
+        // Contract pays deposit from its own balance
+        PromiseOrValue::Promise(
+            ext_voting::ext(VOTING_CONTRACT.parse().unwrap())
+                .with_static_gas(GAS_FOR_GOVERNANCE)
+                .with_attached_deposit(YOCTO_DEPOSIT)
+                .approve_proposal(proposal_id, kind, voting_start_time_sec)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_CALLBACK)
+                        .governance_callback(proposal_id, kind)
+                )
+        )
+    }
+
+    pub fn vote_proposal(&mut self, proposal_id: ProposalId, kind: ProposalKind, vote: Vote) -> Promise {
+        self.require_approved_codehash_for_kind(kind);
+
+        env::log_str(&format!("🤖 PROXY: Agent casting vote on proposal {}", proposal_id));
+
+        self.record_action(proposal_id, kind, HashchainAction::Vote(vote));
 
         // Contract pays deposit from its own balance
         ext_voting::ext(VOTING_CONTRACT.parse().unwrap())
             .with_static_gas(GAS_FOR_GOVERNANCE)
             .with_attached_deposit(YOCTO_DEPOSIT)
-            .approve_proposal(proposal_id, voting_start_time_sec)
+            .vote(proposal_id, kind, vote)
             .then(
                 ext_self::ext(env::current_account_id())
                     .with_static_gas(GAS_FOR_CALLBACK)
-                    .governance_callback(proposal_id)
+                    .vote_callback(proposal_id)
             )
     }
 
@@ -127,6 +274,71 @@ impl Contract {
         env::account_balance()
     }
 
+    pub fn get_endorsements(&self, proposal_id: ProposalId, kind: ProposalKind) -> Vec<AccountId> {
+        self.endorsements_by_proposal_id
+            .get(&(proposal_id, kind))
+            .map(|endorsements| endorsements.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_hashchain_head(&self) -> String {
+        self.head.clone()
+    }
+
+    pub fn get_seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn is_attestation_valid(&self, account_id: AccountId) -> bool {
+        match self.worker_by_account_id.get(&account_id) {
+            Some(worker) => worker.registered_at + self.attestation_ttl_sec >= block_timestamp() / 1000000000,
+            None => false,
+        }
+    }
+
+    pub fn get_events(&self, from_index: u64, limit: u64) -> Vec<(u64, GovernanceEvent)> {
+        (from_index..self.event_count)
+            .take(limit as usize)
+            .filter_map(|index| self.events_by_index.get(&index).map(|event| (index, event.clone())))
+            .collect()
+    }
+
+    // Event helpers
+
+    fn record_event(&mut self, event: GovernanceEvent) {
+        events::emit(&event);
+
+        let index = self.event_count;
+        self.events_by_index.insert(index, event);
+        self.event_count += 1;
+
+        if self.event_count > self.events_capacity {
+            let oldest = self.event_count - self.events_capacity - 1;
+            self.events_by_index.remove(&oldest);
+        }
+    }
+
+    // Hashchain helpers
+
+    fn record_action(&mut self, proposal_id: ProposalId, kind: ProposalKind, action: HashchainAction) {
+        let record = ActionRecord {
+            seq: self.seq,
+            agent: env::predecessor_account_id(),
+            proposal_id,
+            kind,
+            action,
+            block_timestamp: block_timestamp(),
+        };
+        self.head = hashchain::next_head(&self.head, &record);
+        self.seq += 1;
+
+        env::log_str(&format!(
+            "⛓️ HASHCHAIN: {} head={}",
+            serde_json::to_string(&record).expect("failed to serialize action record"),
+            self.head
+        ));
+    }
+
     // Access control helpers
 
     fn require_owner(&mut self) {
@@ -134,8 +346,24 @@ impl Contract {
     }
 
     fn require_approved_codehash(&mut self) {
+        require!(!self.paused, "contract is paused");
         let worker = self.get_agent(env::predecessor_account_id());
         require!(self.approved_codehashes.contains(&worker.codehash));
+        require!(
+            worker.registered_at + self.attestation_ttl_sec >= block_timestamp() / 1000000000,
+            "attestation expired, re-register with a fresh quote"
+        );
+    }
+
+    fn require_approved_codehash_for_kind(&mut self, kind: ProposalKind) {
+        self.require_approved_codehash();
+        let worker = self.get_agent(env::predecessor_account_id());
+        let allowed = self
+            .allowed_codehashes_by_kind
+            .get(&kind)
+            .map(|allowed| allowed.contains(&worker.codehash))
+            .unwrap_or(false);
+        require!(allowed, "codehash is not permitted to act on this proposal kind");
     }
 }
 
@@ -143,14 +371,125 @@ impl Contract {
 #[near]
 impl SelfCallbacks for Contract {
     #[private]
-    fn governance_callback(&mut self, proposal_id: ProposalId, #[callback_result] result: Result<serde_json::Value, PromiseError>) {
+    fn governance_callback(&mut self, proposal_id: ProposalId, kind: ProposalKind, #[callback_result] result: Result<serde_json::Value, PromiseError>) {
         match result {
             Ok(_proposal_info) => {
+                self.endorsements_by_proposal_id.remove(&(proposal_id, kind));
                 env::log_str(&format!("✅ PROXY: Successfully approved proposal {}", proposal_id));
+                self.record_event(GovernanceEvent::ProposalApproved { proposal_id, kind });
             }
             Err(e) => {
+                self.submitted_proposals.remove(&(proposal_id, kind));
                 env::log_str(&format!("❌ PROXY: Failed to approve proposal {}: {:?}", proposal_id, e));
+                self.record_event(GovernanceEvent::ProposalFailed {
+                    proposal_id,
+                    reason: format!("{:?}", e),
+                });
+            }
+        }
+    }
+
+    #[private]
+    fn vote_callback(&mut self, proposal_id: ProposalId, #[callback_result] result: Result<serde_json::Value, PromiseError>) {
+        match result {
+            Ok(_proposal_info) => {
+                env::log_str(&format!("✅ PROXY: Successfully voted on proposal {}", proposal_id));
+            }
+            Err(e) => {
+                env::log_str(&format!("❌ PROXY: Failed to vote on proposal {}: {:?}", proposal_id, e));
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn context(predecessor: AccountId) -> near_sdk::VMContext {
+        VMContextBuilder::new().predecessor_account_id(predecessor).build()
+    }
+
+    fn register_worker(contract: &mut Contract, account_id: AccountId, codehash: &str) {
+        contract.worker_by_account_id.insert(
+            account_id,
+            Worker {
+                checksum: "checksum".to_string(),
+                codehash: codehash.to_string(),
+                registered_at: 0,
+            },
+        );
+    }
+
+    #[test]
+    fn quorum_forwards_proposal_exactly_once() {
+        let owner = accounts(0);
+        let agent_a = accounts(1);
+        let agent_b = accounts(2);
+        let codehash = "codehash-a".to_string();
+
+        testing_env!(context(owner.clone()));
+        let mut contract = Contract::init(owner.clone());
+        contract.set_threshold(2);
+        contract.approve_codehash(codehash.clone());
+        register_worker(&mut contract, agent_a.clone(), &codehash);
+        register_worker(&mut contract, agent_b.clone(), &codehash);
+
+        testing_env!(context(agent_a.clone()));
+        let first = contract.approve_proposal(42, ProposalKind::Default, None);
+        assert!(matches!(first, PromiseOrValue::Value(false)));
+
+        testing_env!(context(agent_b.clone()));
+        let second = contract.approve_proposal(42, ProposalKind::Default, None);
+        assert!(matches!(second, PromiseOrValue::Promise(_)));
+
+        // Quorum was already reached and forwarded above; a repeat call (even from an
+        // already-counted agent) must not forward a second time before the callback lands.
+        testing_env!(context(agent_a.clone()));
+        let third = contract.approve_proposal(42, ProposalKind::Default, None);
+        assert!(matches!(third, PromiseOrValue::Value(false)));
+    }
+
+    #[test]
+    fn revoke_codehash_purges_every_kind_allow_list() {
+        let owner = accounts(0);
+        let codehash = "codehash-b".to_string();
+
+        testing_env!(context(owner.clone()));
+        let mut contract = Contract::init(owner.clone());
+        contract.approve_codehash(codehash.clone());
+        contract.allow_codehash_for_kind(ProposalKind::Funding, codehash.clone());
+
+        contract.revoke_codehash(codehash.clone());
+
+        assert!(!contract
+            .allowed_codehashes_by_kind
+            .get(&ProposalKind::Default)
+            .unwrap()
+            .contains(&codehash));
+        assert!(!contract
+            .allowed_codehashes_by_kind
+            .get(&ProposalKind::Funding)
+            .unwrap()
+            .contains(&codehash));
+    }
+
+    #[test]
+    #[should_panic(expected = "contract is paused")]
+    fn paused_contract_blocks_approve_proposal() {
+        let owner = accounts(0);
+        let agent = accounts(1);
+        let codehash = "codehash-c".to_string();
+
+        testing_env!(context(owner.clone()));
+        let mut contract = Contract::init(owner.clone());
+        contract.approve_codehash(codehash.clone());
+        register_worker(&mut contract, agent.clone(), &codehash);
+        contract.set_paused(true);
+
+        testing_env!(context(agent));
+        contract.approve_proposal(42, ProposalKind::Default, None);
+    }
 }
\ No newline at end of file
@@ -1,17 +1,38 @@
-use near_sdk::{ext_contract, Promise, PromiseError};
+use near_sdk::{ext_contract, near, Promise, PromiseError};
 
 pub type ProposalId = u32;
 
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    Yes,
+    No,
+    Abstain,
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProposalKind {
+    Default,
+    Funding,
+    ParameterChange,
+}
+
 // External contract interfaces
 #[allow(dead_code)]
 #[ext_contract(ext_voting)]
 pub trait VotingContract {
     #[payable]
-    fn approve_proposal(&mut self, proposal_id: ProposalId, voting_start_time_sec: Option<u32>) -> Promise;
+    fn approve_proposal(&mut self, proposal_id: ProposalId, kind: ProposalKind, voting_start_time_sec: Option<u32>) -> Promise;
+
+    #[payable]
+    fn vote(&mut self, proposal_id: ProposalId, kind: ProposalKind, vote: Vote) -> Promise;
 }
 
 #[allow(dead_code)]
 #[ext_contract(ext_self)]
 pub trait SelfCallbacks {
-    fn governance_callback(&mut self, proposal_id: ProposalId, #[callback_result] result: Result<serde_json::Value, PromiseError>);
+    fn governance_callback(&mut self, proposal_id: ProposalId, kind: ProposalKind, #[callback_result] result: Result<serde_json::Value, PromiseError>);
+
+    fn vote_callback(&mut self, proposal_id: ProposalId, #[callback_result] result: Result<serde_json::Value, PromiseError>);
 }
\ No newline at end of file